@@ -10,7 +10,10 @@ const WEBCRYPT_APP_CREDENTIALS_COUNT_LIMIT: u16 = 50;
 
 use apdu_dispatch::{response::SIZE as ApduResponseSize, App as ApduApp};
 use bitflags::bitflags;
-use core::marker::PhantomData;
+use core::{
+    marker::PhantomData,
+    sync::atomic::{AtomicU32, Ordering},
+};
 use ctaphid_dispatch::{app::App as CtaphidApp, MESSAGE_SIZE as CTAPHID_MESSAGE_SIZE};
 #[cfg(feature = "se050")]
 use embedded_hal::blocking::delay::DelayUs;
@@ -68,6 +71,8 @@ pub struct Config {
     #[cfg(feature = "piv-authenticator")]
     #[serde(default, rename = "p", skip_serializing_if = "is_default")]
     piv: PivConfig,
+    #[serde(default, rename = "u", skip_serializing_if = "is_default")]
+    ui: UiConfig,
     #[serde(default, rename = "v", skip_serializing_if = "is_default")]
     fs_version: u32,
     #[cfg(feature = "se050")]
@@ -83,6 +88,7 @@ impl admin_app::Config for Config {
             "opcard" => self.opcard.field(key),
             #[cfg(feature = "piv-authenticator")]
             "piv" => self.piv.field(key),
+            "ui" => self.ui.field(key),
             _ => None,
         }
     }
@@ -121,6 +127,38 @@ impl admin_app::Config for Config {
                 destructive: false,
                 ty: FieldType::Bool,
             },
+            ConfigField {
+                name: "ui.stealth",
+                requires_touch_confirmation: false,
+                // The LED settings are only published when the apps are initialized
+                requires_reboot: true,
+                destructive: false,
+                ty: FieldType::Bool,
+            },
+            ConfigField {
+                name: "ui.brightness",
+                requires_touch_confirmation: false,
+                // The LED settings are only published when the apps are initialized
+                requires_reboot: true,
+                destructive: false,
+                ty: FieldType::U8,
+            },
+            ConfigField {
+                name: "ui.processing_color",
+                requires_touch_confirmation: false,
+                // The LED settings are only published when the apps are initialized
+                requires_reboot: true,
+                destructive: false,
+                ty: FieldType::U8,
+            },
+            ConfigField {
+                name: "ui.touch_color",
+                requires_touch_confirmation: false,
+                // The LED settings are only published when the apps are initialized
+                requires_reboot: true,
+                destructive: false,
+                ty: FieldType::U8,
+            },
         ]
     }
 
@@ -315,6 +353,103 @@ pub struct PivConfig {
     disabled: bool,
 }
 
+#[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct UiConfig {
+    #[serde(default, rename = "s", skip_serializing_if = "is_default")]
+    stealth: bool,
+    /// Brightness in percent, 0 means full brightness
+    #[serde(default, rename = "b", skip_serializing_if = "is_default")]
+    brightness: u8,
+    #[serde(default, rename = "p", skip_serializing_if = "is_default")]
+    processing_color: u8,
+    #[serde(default, rename = "t", skip_serializing_if = "is_default")]
+    touch_color: u8,
+}
+
+impl UiConfig {
+    fn field(&mut self, key: &str) -> Option<ConfigValueMut<'_>> {
+        match key {
+            "stealth" => Some(ConfigValueMut::Bool(&mut self.stealth)),
+            "brightness" => Some(ConfigValueMut::U8(&mut self.brightness)),
+            "processing_color" => Some(ConfigValueMut::U8(&mut self.processing_color)),
+            "touch_color" => Some(ConfigValueMut::U8(&mut self.touch_color)),
+            _ => None,
+        }
+    }
+
+    fn led_settings(&self) -> LedSettings {
+        // 0 is the default value.  The minimum keeps errors and requests for user presence visible.
+        let brightness = match self.brightness {
+            0 => 100,
+            brightness => brightness.clamp(LedSettings::MIN_BRIGHTNESS, 100),
+        };
+        LedSettings {
+            stealth: self.stealth,
+            brightness,
+            processing_color: self.processing_color,
+            touch_color: self.touch_color,
+        }
+    }
+}
+
+/// LED settings from the config, see [`led_settings`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LedSettings {
+    /// Only use the LED for errors and requests for user presence
+    pub stealth: bool,
+    /// Brightness in percent, between `MIN_BRIGHTNESS` and 100
+    pub brightness: u8,
+    /// Color index for processing requests, 0 means the default color
+    pub processing_color: u8,
+    /// Color index for requests for user presence, winking and startup, 0 means the default
+    /// color
+    pub touch_color: u8,
+}
+
+impl LedSettings {
+    pub const MIN_BRIGHTNESS: u8 = 10;
+
+    const DEFAULT: Self = Self {
+        stealth: false,
+        brightness: 100,
+        processing_color: 0,
+        touch_color: 0,
+    };
+
+    const fn to_bits(self) -> u32 {
+        u32::from_le_bytes([
+            self.stealth as u8,
+            self.brightness,
+            self.processing_color,
+            self.touch_color,
+        ])
+    }
+
+    const fn from_bits(bits: u32) -> Self {
+        let [stealth, brightness, processing_color, touch_color] = bits.to_le_bytes();
+        Self {
+            stealth: stealth != 0,
+            brightness,
+            processing_color,
+            touch_color,
+        }
+    }
+}
+
+impl Default for LedSettings {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+static LED_SETTINGS: AtomicU32 = AtomicU32::new(LedSettings::DEFAULT.to_bits());
+
+/// Returns the LED settings from the config, or the default settings if the apps have not been
+/// initialized yet.
+pub fn led_settings() -> LedSettings {
+    LedSettings::from_bits(LED_SETTINGS.load(Ordering::Relaxed))
+}
+
 pub trait Runner {
     type Syscall: Syscall + Clone + 'static;
 
@@ -352,7 +487,10 @@ type AdminApp<R> = admin_app::App<Client<R>, <R as Runner>::Reboot, AdminStatus,
 #[cfg(feature = "fido-authenticator")]
 type FidoApp<R> = fido_authenticator::Authenticator<fido_authenticator::Conforming, Client<R>>;
 #[cfg(feature = "ndef-app")]
-type NdefApp = ndef_app::App<'static>;
+type NdefApp = ndef_app::App;
+/// URI served by the NDEF app instead of the default link, set at build time.
+#[cfg(feature = "ndef-app")]
+const NDEF_URI: Option<&str> = option_env!("NK3_NDEF_URI");
 #[cfg(feature = "secrets-app")]
 type SecretsApp<R> = secrets_app::Authenticator<Client<R>>;
 #[cfg(feature = "webcrypt")]
@@ -404,7 +542,7 @@ pub struct Apps<R: Runner> {
     #[cfg(feature = "piv-authenticator")]
     piv: Option<PivApp<R>>,
     #[cfg(feature = "provisioner-app")]
-    provisioner: ProvisionerApp<R>,
+    provisioner: Option<ProvisionerApp<R>>,
     #[cfg(feature = "webcrypt")]
     webcrypt: Option<PeekingBypass<'static, FidoApp<R>, WebcryptApp<R>>>,
 }
@@ -534,6 +672,13 @@ impl<R: Runner> Apps<R> {
         data: Data<R>,
     ) -> Self {
         const {
+            #[cfg(feature = "ndef-app")]
+            if let Some(uri) = NDEF_URI {
+                assert!(
+                    uri.len() <= ndef_app::MAX_URI_LEN,
+                    "NK3_NDEF_URI is too long"
+                );
+            }
             validate_mechanisms();
         }
 
@@ -548,7 +693,14 @@ impl<R: Runner> Apps<R> {
 
         let (admin, init_status) = Self::admin_app(runner, trussed_service, client_builder, admin);
 
+        let led_settings = admin.config().ui.led_settings();
+        LED_SETTINGS.store(led_settings.to_bits(), Ordering::Relaxed);
+
         let migrated_successfully = !init_status.contains(InitStatus::MIGRATION_ERROR);
+        // If the RNG health tests failed, the Trussed RNG might be predictable.  Therefore we
+        // disable all apps that generate keys and only keep admin-app to report the error.
+        let rng_is_healthy = !init_status.contains(InitStatus::RNG_HEALTH_ERROR);
+        let apps_enabled = migrated_successfully && rng_is_healthy;
         #[cfg(feature = "opcard")]
         let config_has_error = init_status.contains(InitStatus::CONFIG_ERROR);
 
@@ -556,14 +708,14 @@ impl<R: Runner> Apps<R> {
         // the backend to use (se050 or software).  Therefore we disable the app if a config
         // error occured.
         #[cfg(feature = "opcard")]
-        let opcard = (!config_has_error && migrated_successfully)
+        let opcard = (!config_has_error && apps_enabled)
             .then(|| App::new(runner, client_builder, (), &admin.config().opcard));
         #[cfg(all(feature = "fido-authenticator", not(feature = "webcrypt")))]
-        let fido = migrated_successfully
-            .then(|| App::new(runner, client_builder, fido, &admin.config().fido));
+        let fido =
+            apps_enabled.then(|| App::new(runner, client_builder, fido, &admin.config().fido));
 
         #[cfg(feature = "webcrypt")]
-        let webcrypt_fido_bypass = migrated_successfully.then(|| {
+        let webcrypt_fido_bypass = apps_enabled.then(|| {
             PeekingBypass::new(
                 App::new(runner, client_builder, fido, &admin.config().fido),
                 App::new(runner, client_builder, (), &()),
@@ -571,19 +723,23 @@ impl<R: Runner> Apps<R> {
         });
 
         #[cfg(feature = "secrets-app")]
-        let oath = migrated_successfully.then(|| App::new(runner, client_builder, (), &()));
+        let oath = apps_enabled.then(|| App::new(runner, client_builder, (), &()));
 
         #[cfg(feature = "piv-authenticator")]
-        let piv = migrated_successfully.then(|| App::new(runner, client_builder, (), &()));
+        let piv = apps_enabled.then(|| App::new(runner, client_builder, (), &()));
 
         #[cfg(feature = "provisioner-app")]
-        let provisioner = App::new(runner, client_builder, provisioner, &());
+        let provisioner =
+            rng_is_healthy.then(|| App::new(runner, client_builder, provisioner, &()));
 
         Self {
             #[cfg(all(feature = "fido-authenticator", not(feature = "webcrypt")))]
             fido,
             #[cfg(feature = "ndef-app")]
-            ndef: NdefApp::new(),
+            // The length of NDEF_URI is checked at compile time.
+            ndef: NDEF_URI.map_or_else(NdefApp::new, |uri| {
+                NdefApp::with_ndef(ndef_app::uri_record(uri).unwrap())
+            }),
             #[cfg(feature = "secrets-app")]
             oath,
             #[cfg(feature = "opcard")]
@@ -756,7 +912,9 @@ impl<R: Runner> Apps<R> {
 
         // App 7: provisioner
         #[cfg(feature = "provisioner-app")]
-        apps.push(&mut self.provisioner).ok().unwrap();
+        if let Some(provisioner) = self.provisioner.as_mut() {
+            apps.push(provisioner).ok().unwrap();
+        }
 
         f(&mut apps)
     }
@@ -790,7 +948,9 @@ impl<R: Runner> Apps<R> {
 
         // App 4: provisioner
         #[cfg(feature = "provisioner-app")]
-        apps.push(&mut self.provisioner).ok().unwrap();
+        if let Some(provisioner) = self.provisioner.as_mut() {
+            apps.push(provisioner).ok().unwrap();
+        }
 
         f(&mut apps)
     }
@@ -892,6 +1052,9 @@ bitflags! {
         const SE050_ERROR          = 0b00010000;
         const CONFIG_ERROR         = 0b00100000;
         const RNG_ERROR            = 0b01000000;
+        // Set by the runner if the hardware RNG fails the health tests during boot.  Unlike
+        // RNG_ERROR, this flag is never modified by admin-app.
+        const RNG_HEALTH_ERROR     = 0b10000000;
     }
 }
 
@@ -1272,7 +1435,7 @@ impl<R: Runner> App<R> for ProvisionerApp<R> {
 mod tests {
     #[cfg(feature = "piv-authenticator")]
     use super::PivConfig;
-    use super::{Config, FidoConfig, OpcardConfig};
+    use super::{Config, FidoConfig, LedSettings, OpcardConfig, UiConfig};
     use cbor_smol::cbor_serialize;
 
     #[test]
@@ -1288,6 +1451,12 @@ mod tests {
             },
             #[cfg(feature = "piv-authenticator")]
             piv: PivConfig { disabled: true },
+            ui: UiConfig {
+                stealth: true,
+                brightness: 100,
+                processing_color: 0xff,
+                touch_color: 0xff,
+            },
             fs_version: 1,
         };
         let mut buffer = [0; 1024];
@@ -1297,4 +1466,29 @@ mod tests {
         // LPC55, this could be increased to 128 if necessary.
         assert!(data.len() < 64, "{}: {}", data.len(), hex::encode(data));
     }
+
+    #[test]
+    fn test_led_settings() {
+        assert_eq!(UiConfig::default().led_settings(), LedSettings::default());
+
+        let brightness = |brightness| {
+            let config = UiConfig {
+                brightness,
+                ..Default::default()
+            };
+            config.led_settings().brightness
+        };
+        assert_eq!(brightness(0), 100);
+        assert_eq!(brightness(1), LedSettings::MIN_BRIGHTNESS);
+        assert_eq!(brightness(50), 50);
+        assert_eq!(brightness(200), 100);
+
+        let settings = LedSettings {
+            stealth: true,
+            brightness: 25,
+            processing_color: 3,
+            touch_color: 4,
+        };
+        assert_eq!(LedSettings::from_bits(settings.to_bits()), settings);
+    }
 }
@@ -14,7 +14,10 @@ use nfc_device::Iso14443;
 use rand::{CryptoRng, Rng as _, RngCore, SeedableRng};
 use rand_chacha::ChaCha8Rng;
 use ref_swap::OptionRefSwap;
-use trussed::{interrupt::InterruptFlag, platform::Store as _};
+use trussed::{
+    interrupt::InterruptFlag,
+    platform::{Store as _, UserInterface as _},
+};
 use usb_device::{
     bus::UsbBusAllocator,
     device::{UsbDevice, UsbDeviceBuilder, UsbVidPid},
@@ -24,8 +27,8 @@ use usbd_ctaphid::CtapHid;
 use utils::Version;
 
 use crate::{
-    soc::Soc, store::StoreResources, Apps, Board, Runner, RunnerPlatform, RunnerStore, Trussed,
-    UserInterface,
+    rng::HealthTestedRng, soc::Soc, store::StoreResources, Apps, Board, ErrorCode, Runner,
+    RunnerPlatform, RunnerStore, Trussed, UserInterface,
 };
 
 #[cfg(not(feature = "no-delog"))]
@@ -263,14 +266,21 @@ fn init_se050<
 pub fn init_trussed<B: Board, R: CryptoRng + RngCore>(
     dev_rng: &mut R,
     store: RunnerStore<B>,
-    user_interface: UserInterface<<B::Soc as Soc>::Clock, B::Buttons, B::Led>,
+    mut user_interface: UserInterface<<B::Soc as Soc>::Clock, B::Buttons, B::Led>,
     init_status: &mut InitStatus,
     #[cfg(feature = "trussed-auth")] hw_key: Option<&[u8]>,
     #[cfg(feature = "se050")] se050: Option<(B::Twi, B::Se050Timer)>,
 ) -> Trussed<B> {
+    let _start = user_interface.uptime();
+    let mut dev_rng = HealthTestedRng::new(dev_rng);
+    info_now!(
+        "RNG startup test took {} ms",
+        (user_interface.uptime() - _start).as_millis()
+    );
+
     #[cfg(feature = "se050")]
     let (se050, seed) = if let Some((twi, timer)) = se050 {
-        let (se050, seed) = init_se050(twi, timer, dev_rng, init_status);
+        let (se050, seed) = init_se050(twi, timer, &mut dev_rng, init_status);
         (Some(se050), Some(seed))
     } else {
         (None, None)
@@ -281,7 +291,12 @@ pub fn init_trussed<B: Board, R: CryptoRng + RngCore>(
     // False positive due to cfg
     #[allow(clippy::unnecessary_literal_unwrap)]
     let rng = ChaCha8Rng::from_seed(seed.unwrap_or_else(|| dev_rng.gen()));
-    let _ = init_status;
+
+    if !dev_rng.is_healthy() {
+        error_now!("RNG health test failed");
+        init_status.insert(InitStatus::RNG_HEALTH_ERROR);
+        user_interface.set_permanent_error(ErrorCode::Rng);
+    }
 
     let platform = RunnerPlatform {
         rng,
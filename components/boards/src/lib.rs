@@ -7,6 +7,7 @@ use cortex_m_rt::ExceptionFrame;
 
 pub mod flash;
 pub mod init;
+pub mod rng;
 pub mod runtime;
 pub mod soc;
 pub mod store;
@@ -19,7 +20,10 @@ pub mod nk3xn;
 #[cfg(feature = "board-nkpk")]
 pub mod nkpk;
 
-use core::marker::PhantomData;
+use core::{
+    marker::PhantomData,
+    sync::atomic::{AtomicU8, Ordering},
+};
 
 use apps::Dispatch;
 #[cfg(feature = "se050")]
@@ -152,23 +156,51 @@ impl<S: Soc> Syscall for RunnerSyscall<S> {
     }
 }
 
+/// Classes of errors, signaled by the number of red LED blinks.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[repr(u8)]
+pub enum ErrorCode {
+    Panic = 1,
+    HardFault = 2,
+    Storage = 3,
+    /// Not fatal, shown by the UI after a failed RNG health test during boot.
+    Rng = 4,
+}
+
+static ERROR_CODE: AtomicU8 = AtomicU8::new(ErrorCode::Panic as u8);
+
+/// Sets the error code that is signaled if the firmware panics afterwards.
+pub fn set_error_code(code: ErrorCode) {
+    ERROR_CODE.store(code as u8, Ordering::Relaxed);
+}
+
+fn blink_error_code<B: Board>(blinks: u8) -> ! {
+    // asm::delay waits for at least the given number of core clock cycles, so the timing
+    // depends on the clock configuration when the error occurred and on the loop overhead.
+    // Only the number of blinks is meaningful, see docs/troubleshooting.md.
+    const CYCLES: u32 = 24_000_000;
+    loop {
+        for _ in 0..blinks {
+            B::Led::set_panic_led();
+            cortex_m::asm::delay(CYCLES);
+            B::Led::clear_panic_led();
+            cortex_m::asm::delay(CYCLES);
+        }
+        cortex_m::asm::delay(4 * CYCLES);
+    }
+}
+
 pub fn handle_panic<B: Board>(_info: &core::panic::PanicInfo) -> ! {
     error_now!("{}", _info);
     #[cfg(feature = "rtt-target")]
     rtt_target::rprint!("{}", _info);
-    B::Led::set_panic_led();
-    loop {
-        core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::SeqCst);
-    }
+    blink_error_code::<B>(ERROR_CODE.load(Ordering::Relaxed))
 }
 
 pub fn handle_hard_fault<B: Board>(_ef: &ExceptionFrame) -> ! {
     #[cfg(feature = "rtt-target")]
     rtt_target::rprint!("HardFault: {:?}", _ef);
-    B::Led::set_panic_led();
-    loop {
-        core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::SeqCst);
-    }
+    blink_error_code::<B>(ErrorCode::HardFault as u8)
 }
 
 pub const WATCHDOG_DURATION_SECONDS: u64 = 15 * 60;
@@ -139,6 +139,16 @@ impl rgb_led::RgbLed for RgbLed {
         }
     }
 
+    fn clear_panic_led() {
+        unsafe {
+            let pac = nrf52840_pac::Peripherals::steal();
+            let p0 = nrf52840_hal::gpio::p0::Parts::new(pac.P0);
+
+            // red
+            p0.p0_09.into_push_pull_output(Level::High).degrade();
+        }
+    }
+
     fn red(&mut self, intensity: u8) {
         self.set_led(Color::Red, pwm::Channel::C0, intensity);
     }
@@ -83,6 +83,18 @@ impl rgb_led::RgbLed for RgbLed {
         }
     }
 
+    fn clear_panic_led() {
+        unsafe {
+            let mut syscon = Syscon::steal();
+            let mut iocon = Iocon::steal().enabled(&mut syscon);
+            let mut gpio = Gpio::steal().enabled(&mut syscon);
+
+            RedLedPin::steal()
+                .into_gpio_pin(&mut iocon, &mut gpio)
+                .into_output_high();
+        }
+    }
+
     fn red(&mut self, intensity: u8) {
         self.pwm.set_duty(RedLed::CHANNEL, (intensity / 2) as u16);
     }
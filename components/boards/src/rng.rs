@@ -0,0 +1,255 @@
+//! Health tests for the hardware RNG as described in NIST SP 800-90B, section 4.4.
+//!
+//! The tests only run while the hardware RNG is used during boot, i. e. for the startup test and
+//! for generating the seeds for the SE050 and the ChaCha8 RNG used by Trussed.
+//!
+//! The cutoff values assume a conservative min-entropy of one bit per byte sample, which is far
+//! below what the TRNGs on the supported SoCs provide.  This means that the tests only detect
+//! a total failure of the entropy source, not a slight degradation.
+
+use rand::{rand_core::impls, CryptoRng, Error, RngCore};
+
+/// Cutoff for the repetition count test: `1 + ceil(20 / H)` with `H = 1`.
+const REPETITION_COUNT_CUTOFF: u16 = 21;
+/// Window size for the adaptive proportion test for non-binary samples.
+const ADAPTIVE_PROPORTION_WINDOW: u16 = 512;
+/// Cutoff for the adaptive proportion test for `H = 1` and a window size of 512.
+const ADAPTIVE_PROPORTION_CUTOFF: u16 = 410;
+/// Number of samples that are tested and discarded during the startup test.
+const STARTUP_SAMPLES: usize = 1024;
+
+struct RepetitionCountTest {
+    last: Option<u8>,
+    count: u16,
+}
+
+impl RepetitionCountTest {
+    const fn new() -> Self {
+        Self {
+            last: None,
+            count: 0,
+        }
+    }
+
+    fn check(&mut self, sample: u8) -> bool {
+        if self.last == Some(sample) {
+            self.count = self.count.saturating_add(1);
+        } else {
+            self.last = Some(sample);
+            self.count = 1;
+        }
+        self.count < REPETITION_COUNT_CUTOFF
+    }
+}
+
+struct AdaptiveProportionTest {
+    first: u8,
+    index: u16,
+    count: u16,
+}
+
+impl AdaptiveProportionTest {
+    const fn new() -> Self {
+        Self {
+            first: 0,
+            index: 0,
+            count: 0,
+        }
+    }
+
+    fn check(&mut self, sample: u8) -> bool {
+        if self.index == 0 {
+            self.first = sample;
+            self.count = 1;
+        } else if sample == self.first {
+            self.count = self.count.saturating_add(1);
+        }
+        self.index = (self.index + 1) % ADAPTIVE_PROPORTION_WINDOW;
+        self.count < ADAPTIVE_PROPORTION_CUTOFF
+    }
+}
+
+/// Wrapper for a hardware RNG that runs the startup and continuous health tests on its output.
+///
+/// Once a test failed, the wrapper stays in the failed state.  The output is still passed
+/// through so that the caller can decide how to handle the failure.
+pub struct HealthTestedRng<R> {
+    rng: R,
+    repetition_count: RepetitionCountTest,
+    adaptive_proportion: AdaptiveProportionTest,
+    healthy: bool,
+}
+
+impl<R: RngCore> HealthTestedRng<R> {
+    /// Wraps the given RNG and runs the startup test.
+    pub fn new(rng: R) -> Self {
+        let mut rng = Self {
+            rng,
+            repetition_count: RepetitionCountTest::new(),
+            adaptive_proportion: AdaptiveProportionTest::new(),
+            healthy: true,
+        };
+        let mut buffer = [0; 64];
+        for _ in 0..STARTUP_SAMPLES / buffer.len() {
+            rng.fill_bytes(&mut buffer);
+        }
+        rng
+    }
+
+    /// Returns false if any of the health tests failed for the output generated so far.
+    pub fn is_healthy(&self) -> bool {
+        self.healthy
+    }
+
+    fn check(&mut self, samples: &[u8]) {
+        for sample in samples {
+            let repetition_count = self.repetition_count.check(*sample);
+            let adaptive_proportion = self.adaptive_proportion.check(*sample);
+            if !(repetition_count && adaptive_proportion) {
+                self.healthy = false;
+            }
+        }
+    }
+}
+
+impl<R: RngCore> RngCore for HealthTestedRng<R> {
+    fn next_u32(&mut self) -> u32 {
+        impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest);
+        self.check(dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.rng.try_fill_bytes(dest)?;
+        self.check(dest);
+        Ok(())
+    }
+}
+
+impl<R: CryptoRng + RngCore> CryptoRng for HealthTestedRng<R> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct ConstantRng(u8);
+
+    impl RngCore for ConstantRng {
+        fn next_u32(&mut self) -> u32 {
+            impls::next_u32_via_fill(self)
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            impls::next_u64_via_fill(self)
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            dest.fill(self.0);
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    struct CountingRng(u8);
+
+    impl RngCore for CountingRng {
+        fn next_u32(&mut self) -> u32 {
+            impls::next_u32_via_fill(self)
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            impls::next_u64_via_fill(self)
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            for byte in dest {
+                *byte = self.0;
+                self.0 = self.0.wrapping_add(1);
+            }
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn repetition_count_cutoff() {
+        let mut test = RepetitionCountTest::new();
+        for _ in 0..20 {
+            assert!(test.check(0xaa));
+        }
+        assert!(!test.check(0xaa));
+    }
+
+    #[test]
+    fn repetition_count_saturates() {
+        let mut test = RepetitionCountTest::new();
+        for _ in 0..=u16::MAX {
+            test.check(0xaa);
+        }
+        assert!(!test.check(0xaa));
+    }
+
+    #[test]
+    fn repetition_count_reset() {
+        let mut test = RepetitionCountTest::new();
+        for _ in 0..20 {
+            assert!(test.check(0xaa));
+        }
+        assert!(test.check(0x55));
+        for _ in 0..20 {
+            assert!(test.check(0xaa));
+        }
+        assert!(!test.check(0xaa));
+    }
+
+    #[test]
+    fn adaptive_proportion_cutoff() {
+        let mut test = AdaptiveProportionTest::new();
+        for _ in 0..409 {
+            assert!(test.check(0xaa));
+        }
+        assert!(!test.check(0xaa));
+    }
+
+    #[test]
+    fn adaptive_proportion_window() {
+        let mut test = AdaptiveProportionTest::new();
+        // 409 matches and 103 other samples fill the first window
+        for _ in 0..409 {
+            assert!(test.check(0xaa));
+        }
+        for _ in 0..103 {
+            assert!(test.check(0x55));
+        }
+        // the next window starts with a new sample and a new count
+        for _ in 0..409 {
+            assert!(test.check(0x55));
+        }
+        assert!(!test.check(0x55));
+    }
+
+    #[test]
+    fn health_tested_rng() {
+        let rng = HealthTestedRng::new(ConstantRng(0));
+        assert!(!rng.is_healthy());
+
+        let mut rng = HealthTestedRng::new(CountingRng(0));
+        assert!(rng.is_healthy());
+        let mut buffer = [0; 32];
+        rng.fill_bytes(&mut buffer);
+        assert!(rng.is_healthy());
+    }
+}
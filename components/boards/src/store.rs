@@ -10,7 +10,7 @@ use littlefs2::{
 };
 use trussed::store::Store;
 
-use crate::Board;
+use crate::{set_error_code, Board, ErrorCode};
 
 // 8KB of RAM
 const_ram_storage!(
@@ -143,6 +143,7 @@ pub fn init_store<B: Board>(
         Ok(ifs) => resources.internal.fs.write(ifs),
         Err(_e) => {
             error!("IFS Mount Error {:?}", _e);
+            set_error_code(ErrorCode::Storage);
             panic!("IFS");
         }
     };
@@ -151,6 +152,7 @@ pub fn init_store<B: Board>(
         Ok(efs) => resources.external.fs.write(efs),
         Err(_e) => {
             error!("EFS Mount Error {:?}", _e);
+            set_error_code(ErrorCode::Storage);
             panic!("EFS");
         }
     };
@@ -159,6 +161,7 @@ pub fn init_store<B: Board>(
         Ok(vfs) => resources.volatile.fs.write(vfs),
         Err(_e) => {
             error!("VFS Mount Error {:?}", _e);
+            set_error_code(ErrorCode::Storage);
             panic!("VFS");
         }
     };
@@ -4,11 +4,14 @@ use core::{
     time::Duration,
 };
 
+use apps::LedSettings;
 use trussed::platform::{self, consent, ui};
 
 use buttons::UserPresence;
 use rgb_led::{Intensities, RgbLed};
 
+use crate::ErrorCode;
+
 pub mod buttons;
 pub mod rgb_led;
 
@@ -33,6 +36,41 @@ const WHITE: Intensities = Intensities {
     blue: u8::MAX,
 };
 
+const GREEN: Intensities = Intensities {
+    red: 0,
+    green: u8::MAX,
+    blue: 0,
+};
+const BLUE: Intensities = Intensities {
+    red: 0,
+    green: 0,
+    blue: u8::MAX,
+};
+const YELLOW: Intensities = Intensities {
+    red: u8::MAX,
+    green: u8::MAX,
+    blue: 0,
+};
+const MAGENTA: Intensities = Intensities {
+    red: u8::MAX,
+    green: 0,
+    blue: u8::MAX,
+};
+
+/// Returns the color for an index set with the `ui.processing_color` or `ui.touch_color` config
+/// options.  Red is reserved for errors.
+fn palette_color(index: u8, default: Intensities) -> Intensities {
+    match index {
+        1 => WHITE,
+        2 => TEAL,
+        3 => GREEN,
+        4 => BLUE,
+        5 => YELLOW,
+        6 => MAGENTA,
+        _ => default,
+    }
+}
+
 static WAITING: AtomicBool = AtomicBool::new(false);
 
 fn set_waiting(waiting: bool) {
@@ -43,6 +81,13 @@ pub fn is_waiting() -> bool {
     WAITING.load(Relaxed)
 }
 
+static FADING: AtomicBool = AtomicBool::new(false);
+
+/// Returns true if the LED is fading, so that the runner should refresh the UI more often.
+pub fn is_fading() -> bool {
+    FADING.load(Relaxed)
+}
+
 pub trait Clock {
     fn uptime(&mut self) -> Duration;
 }
@@ -53,6 +98,7 @@ pub struct UserInterface<C, P, L> {
     rgb: Option<L>,
     status: Status,
     provisioner: bool,
+    permanent_error: Option<ErrorCode>,
 }
 
 impl<C: Clock, P: UserPresence, L: RgbLed> UserInterface<C, P, L> {
@@ -72,16 +118,33 @@ impl<C: Clock, P: UserPresence, L: RgbLed> UserInterface<C, P, L> {
             status,
             rgb,
             provisioner,
+            permanent_error: None,
         };
         ui.refresh_ui(uptime);
         ui
     }
 
+    /// Blinks the given error code until the next reboot, e. g. after a failed self-test.
+    ///
+    /// Requests for user presence are still shown so that the device remains usable.
+    pub fn set_permanent_error(&mut self, code: ErrorCode) {
+        self.permanent_error = Some(code);
+        let uptime = self.clock.uptime();
+        self.refresh_ui(uptime);
+    }
+
     fn refresh_ui(&mut self, uptime: Duration) {
         if let Some(rgb) = &mut self.rgb {
             self.status.refresh(uptime);
-            let mode = self.status.led_mode(self.provisioner);
-            rgb.set(mode.color(uptime));
+            let settings = apps::led_settings();
+            let mode = match self.permanent_error {
+                Some(code) if !matches!(self.status, Status::WaitingForUserPresence(_)) => {
+                    LedMode::error_code(code, Duration::ZERO)
+                }
+                _ => self.status.led_mode(self.provisioner, &settings),
+            };
+            FADING.store(matches!(mode, LedMode::Breathing { .. }), Relaxed);
+            rgb.set(mode.color(uptime).scale_by(settings.brightness));
         }
     }
 }
@@ -128,13 +191,21 @@ impl CustomStatus {
             apps::CustomStatus::ReverseHotpSuccess => TEAL,
             apps::CustomStatus::ReverseHotpError => RED,
         };
-        LedMode::simple_blinking(color, start)
+        // blink faster than for requests for user presence, which might use the same color
+        LedMode::blinking(color, BLACK, Duration::from_millis(250), start)
     }
 
     fn allow_update(&self) -> bool {
         false
     }
 
+    fn is_error(&self) -> bool {
+        match self.0 {
+            apps::CustomStatus::ReverseHotpSuccess => false,
+            apps::CustomStatus::ReverseHotpError => true,
+        }
+    }
+
     fn duration(&self) -> Option<Duration> {
         match self.0 {
             apps::CustomStatus::ReverseHotpSuccess => Some(Duration::from_secs(10)),
@@ -197,20 +268,34 @@ impl Status {
         }
     }
 
-    pub fn led_mode(&self, is_provisioner: bool) -> LedMode {
+    /// Returns true if the status is also shown in stealth mode.
+    fn is_important(&self) -> bool {
         match self {
-            Self::Startup(_) => LedMode::constant(WHITE),
+            Self::WaitingForUserPresence(_) | Self::Error => true,
+            Self::Custom { status, .. } => status.is_error(),
+            _ => false,
+        }
+    }
+
+    pub fn led_mode(&self, is_provisioner: bool, settings: &LedSettings) -> LedMode {
+        if settings.stealth && !self.is_important() {
+            return LedMode::constant(BLACK);
+        }
+        let processing = palette_color(settings.processing_color, TEAL);
+        let touch = palette_color(settings.touch_color, WHITE);
+        match self {
+            Self::Startup(_) => LedMode::constant(touch),
             Self::Idle => {
                 if is_provisioner {
-                    LedMode::constant(WHITE)
+                    LedMode::constant(touch)
                 } else {
                     LedMode::constant(BLACK)
                 }
             }
-            Self::Processing => LedMode::constant(TEAL),
-            Self::WaitingForUserPresence(start) => LedMode::simple_blinking(WHITE, *start),
+            Self::Processing => LedMode::constant(processing),
+            Self::WaitingForUserPresence(start) => LedMode::simple_blinking(touch, *start),
             Self::Error => LedMode::constant(RED),
-            Self::Winking(range) => LedMode::simple_blinking(WHITE, range.start),
+            Self::Winking(range) => LedMode::breathing(touch, range.start),
             Self::Custom { status, start } => status.led_mode(*start),
         }
     }
@@ -250,6 +335,16 @@ pub enum LedMode {
         period: Duration,
         start: Duration,
     },
+    Breathing {
+        color: Intensities,
+        period: Duration,
+        start: Duration,
+    },
+    /// Blinks red `blinks` times, followed by a pause, to show an [`ErrorCode`].
+    ErrorCode {
+        blinks: u8,
+        start: Duration,
+    },
 }
 
 impl LedMode {
@@ -275,6 +370,21 @@ impl LedMode {
         Self::blinking(color, BLACK, Duration::from_millis(500), start)
     }
 
+    pub fn breathing(color: Intensities, start: Duration) -> Self {
+        Self::Breathing {
+            color,
+            period: Duration::from_millis(1500),
+            start,
+        }
+    }
+
+    pub fn error_code(code: ErrorCode, start: Duration) -> Self {
+        Self::ErrorCode {
+            blinks: code as u8,
+            start,
+        }
+    }
+
     pub fn color(&self, uptime: Duration) -> Intensities {
         match self {
             Self::Constant { color } => *color,
@@ -292,6 +402,72 @@ impl LedMode {
                     *off_color
                 }
             }
+            Self::Breathing {
+                color,
+                period,
+                start,
+            } => {
+                // fade in and out linearly, starting from off
+                let period = period.as_millis();
+                let half = period / 2;
+                let delta = (uptime - *start).as_millis() % period;
+                let level = delta.min(period - delta);
+                let scale = |intensity: u8| (u128::from(intensity) * level / half) as u8;
+                Intensities {
+                    red: scale(color.red),
+                    green: scale(color.green),
+                    blue: scale(color.blue),
+                }
+            }
+            Self::ErrorCode { blinks, start } => {
+                // each blink is on and off for one step, the pause is four steps long
+                let steps = 2 * u128::from(*blinks) + 4;
+                let step = (uptime - *start).as_millis() / 250 % steps;
+                if step < 2 * u128::from(*blinks) && step % 2 == 0 {
+                    RED
+                } else {
+                    BLACK
+                }
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::{ErrorCode, Intensities, LedMode, BLACK, RED, TEAL};
+
+    #[test]
+    fn breathing() {
+        let start = Duration::from_secs(10);
+        let mode = LedMode::breathing(TEAL, start);
+        assert_eq!(mode.color(start), BLACK);
+        assert_eq!(
+            mode.color(start + Duration::from_millis(375)),
+            Intensities {
+                red: 0,
+                green: 127,
+                blue: 45,
+            }
+        );
+        assert_eq!(mode.color(start + Duration::from_millis(750)), TEAL);
+        assert_eq!(mode.color(start + Duration::from_millis(1500)), BLACK);
+        assert_eq!(mode.color(start + Duration::from_millis(2250)), TEAL);
+    }
+
+    #[test]
+    fn error_code() {
+        let start = Duration::from_secs(10);
+        let mode = LedMode::error_code(ErrorCode::Storage, start);
+        let colors: [_; 10] = core::array::from_fn(|i| {
+            mode.color(start + Duration::from_millis(250 * i as u64 + 100))
+        });
+        assert_eq!(
+            colors,
+            [RED, BLACK, RED, BLACK, RED, BLACK, BLACK, BLACK, BLACK, BLACK]
+        );
+        assert_eq!(mode.color(start + Duration::from_millis(2500)), RED);
+    }
+}
@@ -24,8 +24,8 @@ impl From<u32> for Intensities {
 }
 
 impl Intensities {
-    pub fn scale_by(&mut self, percent: &u8) -> Self {
-        let scale: f32 = (percent / 100).into();
+    pub fn scale_by(self, percent: u8) -> Self {
+        let scale = f32::from(percent) / 100.0;
         Intensities {
             red: (self.red as f32 * scale) as u8,
             green: (self.green as f32 * scale) as u8,
@@ -37,6 +37,9 @@ impl Intensities {
 pub trait RgbLed {
     fn set_panic_led();
 
+    /// Turn off the LED again after `set_panic_led`, e. g. to blink an error code
+    fn clear_panic_led();
+
     /// Set all LEDs
     fn set(&mut self, intensities: Intensities) {
         self.red(intensities.red);
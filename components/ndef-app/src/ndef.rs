@@ -1,19 +1,95 @@
 use apdu_app::{CommandView, Data, Interface};
+use heapless::Vec;
 use iso7816::{Instruction, Status};
 
-pub struct App<'a> {
-    reader: &'a [u8],
+/// Maximum size of the NDEF file, as announced in the capability container.
+pub const MAX_NDEF_FILE_SIZE: usize = 0x7f;
+
+/// Maximum length of a URI that fits into the NDEF file even without prefix compression.
+pub const MAX_URI_LEN: usize = MAX_NDEF_FILE_SIZE - 7;
+
+/// Contents of the NDEF file: a two-byte length followed by the NDEF message.
+pub type NdefFile = Vec<u8, MAX_NDEF_FILE_SIZE>;
+
+/// URI identifier codes from the NFC Forum URI Record Type Definition.
+///
+/// Longer prefixes must come first so that the best match is used.
+const URI_PREFIXES: &[(u8, &str)] = &[
+    (0x01, "http://www."),
+    (0x02, "https://www."),
+    (0x03, "http://"),
+    (0x04, "https://"),
+    (0x05, "tel:"),
+    (0x06, "mailto:"),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The record does not fit into the NDEF file.
+    TooLong,
+    /// The language code is longer than 63 bytes.
+    InvalidLanguage,
 }
 
-impl<'a> App<'a> {
-    pub const CAPABILITY_CONTAINER: [u8; 15] = [
-        0x00, 0x0f, /* CCEN_HI, CCEN_LOW */
-        0x20, /* VERSION */
-        0x00, 0x7f, /* MLe_HI, MLe_LOW */
-        0x00, 0x7f, /* MLc_HI, MLc_LOW */
-        /* TLV */
-        0x04, 0x06, 0xe1, 0x04, 0x00, 0x7f, 0x00, 0x00,
-    ];
+/// Creates an NDEF file with a single well-known record.
+fn record(record_type: u8, payload: &[&[u8]]) -> Result<NdefFile, Error> {
+    let payload_len: usize = payload.iter().map(|part| part.len()).sum();
+    // header, type length, payload length, type
+    let message_len = 4 + payload_len;
+
+    let mut file = NdefFile::new();
+    file.extend_from_slice(&[0x00, 0x00, 0xd1, 0x01, 0x00, record_type])
+        .map_err(|_| Error::TooLong)?;
+    for part in payload {
+        file.extend_from_slice(part).map_err(|_| Error::TooLong)?;
+    }
+    // both fit into a byte as the file is at most 0x7f bytes long
+    file[1] = message_len as u8;
+    file[4] = payload_len as u8;
+    Ok(file)
+}
+
+/// Creates an NDEF file with a URI record, e.g. `https://www.nitrokey.com/`.
+pub fn uri_record(uri: &str) -> Result<NdefFile, Error> {
+    let (code, rest) = URI_PREFIXES
+        .iter()
+        .find_map(|(code, prefix)| uri.strip_prefix(prefix).map(|rest| (*code, rest)))
+        .unwrap_or((0x00, uri));
+    record(b'U', &[&[code], rest.as_bytes()])
+}
+
+/// Creates an NDEF file with a UTF-8 text record, using an IANA language code like `en`.
+pub fn text_record(language: &str, text: &str) -> Result<NdefFile, Error> {
+    if language.len() > 0x3f {
+        return Err(Error::InvalidLanguage);
+    }
+    let status = language.len() as u8;
+    record(b'T', &[&[status], language.as_bytes(), text.as_bytes()])
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum File {
+    CapabilityContainer,
+    Ndef,
+}
+
+pub struct App {
+    ndef: NdefFile,
+    selected: File,
+}
+
+impl App {
+    pub const CAPABILITY_CONTAINER: [u8; 15] = {
+        let [hi, lo] = (MAX_NDEF_FILE_SIZE as u16).to_be_bytes();
+        [
+            0x00, 0x0f, /* CCEN_HI, CCEN_LOW */
+            0x20, /* VERSION */
+            hi, lo, /* MLe_HI, MLe_LOW */
+            hi, lo, /* MLc_HI, MLc_LOW */
+            /* TLV */
+            0x04, 0x06, 0xe1, 0x04, hi, lo, 0x00, 0x00,
+        ]
+    };
 
     pub const NDEF: [u8; 20] = [
         0x00, 0x12, /* two-byte length */
@@ -26,26 +102,41 @@ impl<'a> App<'a> {
         0x2f, /* nitrokey.com/ */
     ];
 
-    pub fn new() -> App<'a> {
+    /// Creates an app serving the default record with the link to `https://www.nitrokey.com/`.
+    pub fn new() -> App {
+        // NDEF is shorter than MAX_NDEF_FILE_SIZE
+        Self::with_ndef(NdefFile::from_slice(&Self::NDEF).unwrap())
+    }
+
+    /// Creates an app serving the given NDEF file, see [`uri_record`] and [`text_record`].
+    pub fn with_ndef(ndef: NdefFile) -> App {
         App {
-            reader: &Self::NDEF,
+            ndef,
+            selected: File::Ndef,
+        }
+    }
+
+    fn selected_file(&self) -> &[u8] {
+        match self.selected {
+            File::CapabilityContainer => &Self::CAPABILITY_CONTAINER,
+            File::Ndef => &self.ndef,
         }
     }
 }
 
-impl Default for App<'_> {
+impl Default for App {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl iso7816::App for App<'_> {
+impl iso7816::App for App {
     fn aid(&self) -> iso7816::Aid {
         iso7816::Aid::new(&[0xD2u8, 0x76, 0x00, 0x00, 0x85, 0x01, 0x01])
     }
 }
 
-impl<const R: usize> apdu_app::App<R> for App<'_> {
+impl<const R: usize> apdu_app::App<R> for App {
     fn select(
         &mut self,
         _interface: Interface,
@@ -72,27 +163,31 @@ impl<const R: usize> apdu_app::App<R> for App<'_> {
         match instruction {
             Instruction::Select => {
                 if payload.starts_with(&[0xE1u8, 0x03]) {
-                    self.reader = &Self::CAPABILITY_CONTAINER;
+                    self.selected = File::CapabilityContainer;
                     Ok(())
                 } else if payload.starts_with(&[0xE1u8, 0x04]) {
-                    self.reader = &Self::NDEF;
+                    self.selected = File::Ndef;
                     Ok(())
                 } else {
                     Err(Status::NotFound)
                 }
             }
             Instruction::ReadBinary => {
+                let reader = self.selected_file();
                 let offset = (((p1 & 0xef) as usize) << 8) | p2 as usize;
-                let len_to_read = if expected > (self.reader.len() - offset) {
-                    self.reader.len() - offset
+                if offset > reader.len() {
+                    return Err(Status::IncorrectP1OrP2Parameter);
+                }
+                let len_to_read = if expected > (reader.len() - offset) {
+                    reader.len() - offset
                 } else if expected > 0 {
                     expected
                 } else {
-                    self.reader.len() - offset
+                    reader.len() - offset
                 };
 
                 reply
-                    .extend_from_slice(&self.reader[offset..offset + len_to_read])
+                    .extend_from_slice(&reader[offset..offset + len_to_read])
                     .ok();
                 Ok(())
             }
@@ -100,3 +195,84 @@ impl<const R: usize> apdu_app::App<R> for App<'_> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::convert::TryFrom;
+
+    fn read_binary(app: &mut App, offset: u16) -> Result<Data<256>, Status> {
+        let [p1, p2] = offset.to_be_bytes();
+        let apdu = [0x00, 0xb0, p1, p2, 0x00];
+        let command = CommandView::try_from(&apdu[..]).unwrap();
+        let mut reply = Data::new();
+        apdu_app::App::<256>::call(app, Interface::Contactless, command, &mut reply)?;
+        Ok(reply)
+    }
+
+    #[test]
+    fn default_uri() {
+        let file = uri_record("https://www.nitrokey.com/").unwrap();
+        assert_eq!(file.as_slice(), App::NDEF.as_slice());
+    }
+
+    #[test]
+    fn uri_prefixes() {
+        let file = uri_record("https://example.com").unwrap();
+        assert_eq!(&file[..7], &[0x00, 0x10, 0xd1, 0x01, 0x0c, b'U', 0x04]);
+        assert_eq!(&file[7..], b"example.com");
+
+        let file = uri_record("geo:0,0").unwrap();
+        assert_eq!(&file[..7], &[0x00, 0x0c, 0xd1, 0x01, 0x08, b'U', 0x00]);
+        assert_eq!(&file[7..], b"geo:0,0");
+    }
+
+    #[test]
+    fn text() {
+        let file = text_record("en", "Nitrokey 3").unwrap();
+        assert_eq!(
+            &file[..9],
+            &[0x00, 0x11, 0xd1, 0x01, 0x0d, b'T', 0x02, b'e', b'n']
+        );
+        assert_eq!(&file[9..], b"Nitrokey 3");
+
+        assert_eq!(
+            text_record(core::str::from_utf8(&[b'a'; 64]).unwrap(), ""),
+            Err(Error::InvalidLanguage)
+        );
+    }
+
+    #[test]
+    fn capability_container() {
+        assert_eq!(
+            App::CAPABILITY_CONTAINER,
+            [
+                0x00, 0x0f, 0x20, 0x00, 0x7f, 0x00, 0x7f, 0x04, 0x06, 0xe1, 0x04, 0x00, 0x7f, 0x00,
+                0x00,
+            ]
+        );
+    }
+
+    #[test]
+    fn too_long() {
+        let uri = [b'a'; MAX_URI_LEN];
+        assert!(uri_record(core::str::from_utf8(&uri).unwrap()).is_ok());
+        let uri = [b'a'; MAX_URI_LEN + 1];
+        assert_eq!(
+            uri_record(core::str::from_utf8(&uri).unwrap()),
+            Err(Error::TooLong)
+        );
+    }
+
+    #[test]
+    fn read_binary_offset() {
+        let mut app = App::new();
+        assert_eq!(read_binary(&mut app, 0).unwrap(), &App::NDEF[..]);
+        assert_eq!(read_binary(&mut app, 18).unwrap(), &App::NDEF[18..]);
+        assert!(read_binary(&mut app, 20).unwrap().is_empty());
+        assert_eq!(
+            read_binary(&mut app, 21),
+            Err(Status::IncorrectP1OrP2Parameter)
+        );
+    }
+}
@@ -278,7 +278,9 @@ mod app {
         trussed.lock(|trussed| {
             trussed.update_ui();
         });
-        ui::spawn_after(RtcDuration::from_ms(125)).ok();
+        // refresh more often while the LED fades, otherwise the fade is visibly stepped
+        let interval = if boards::ui::is_fading() { 25 } else { 125 };
+        ui::spawn_after(RtcDuration::from_ms(interval)).ok();
     }
 
     #[task(priority = 6, binds = WDT)]